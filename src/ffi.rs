@@ -1,7 +1,9 @@
 use bitmap::IntHwlocBitmap;
-use libc::{c_char, c_int, c_uint, c_ulonglong};
+use libc::{c_char, c_int, c_uint, c_ulonglong, c_void, size_t};
 use num::{FromPrimitive, ToPrimitive};
 use std::cmp::{Ordering, PartialOrd};
+use std::ffi::{CStr, CString};
+use std::ops::{BitAnd, BitOr, BitXor, Not, Sub};
 use support::TopologySupport;
 use topology_object::TopologyObject;
 use {pid_t, pthread_t};
@@ -115,24 +117,29 @@ pub enum ObjectType {
     TypeMax,
 }
 
+/// Sentinel returned by `hwloc_compare_types` (defined as `INT_MAX` in
+/// hwloc.h) when the two types have no nesting relationship, e.g. a
+/// `NUMANode` compared against a `PCIDevice`.
+const HWLOC_TYPE_UNORDERED: c_int = c_int::max_value();
+
 impl PartialOrd for ObjectType {
     fn partial_cmp(&self, other: &Self) -> Option<Ordering> {
         let compared = unsafe { hwloc_compare_types(self.clone(), other.clone()) };
+        if compared == HWLOC_TYPE_UNORDERED {
+            return None;
+        }
         match compared {
             c if c < 0 => Some(Ordering::Less),
             c if c == 0 => Some(Ordering::Equal),
-            c if c > 0 => Some(Ordering::Greater),
-            _ => None,
+            _ => Some(Ordering::Greater),
         }
     }
 }
 
 impl PartialEq for ObjectType {
     fn eq(&self, other: &Self) -> bool {
-        match self.partial_cmp(other) {
-            Some(Ordering::Equal) => true,
-            _ => false,
-        }
+        let compared = unsafe { hwloc_compare_types(self.clone(), other.clone()) };
+        compared == 0
     }
 }
 
@@ -158,7 +165,27 @@ pub enum TypeDepthError {
     Unkown = -99,
 }
 
-#[derive(Debug, PartialEq)]
+impl TypeDepthError {
+    /// Returns the raw negative `depth` hwloc uses for this error. For the
+    /// virtual-depth variants (`TypeDepthNumaNode`..`TypeDepthMemcache`),
+    /// this is a real depth that can be fed back into `type_at_depth`,
+    /// `num_objects_at_depth` and `objects_at_depth` to reach those objects.
+    pub fn as_raw_depth(&self) -> i32 {
+        match *self {
+            TypeDepthError::TypeDepthUnknown => -1,
+            TypeDepthError::TypeDepthMultiple => -2,
+            TypeDepthError::TypeDepthNumaNode => -3,
+            TypeDepthError::TypeDepthBridge => -4,
+            TypeDepthError::TypeDepthPCIDevice => -5,
+            TypeDepthError::TypeDepthOSDevice => -6,
+            TypeDepthError::TypeDepthMisc => -7,
+            TypeDepthError::TypeDepthMemcache => -8,
+            TypeDepthError::Unkown => -99,
+        }
+    }
+}
+
+#[derive(Debug, Clone, Copy, PartialEq)]
 pub enum CacheType {
     Unified,
     Data,
@@ -225,6 +252,94 @@ impl FromPrimitive for TopologyFlag {
     }
 }
 
+/// The memory binding policy to apply, passed to the `*_membind` family of
+/// functions.
+#[repr(u32)]
+#[derive(Debug, Clone, Copy, PartialEq)]
+pub enum MemBindPolicy {
+    /// Reset the memory allocation policy to the system default.
+    Default,
+    /// Allocate memory on the NUMA node where the thread touching it runs.
+    FirstTouch,
+    /// Allocate memory on the given nodeset.
+    Bind,
+    /// Allocate memory on the given nodeset, interleaved across nodes.
+    Interleave,
+    /// Move memory pages to the NUMA node where the thread touching them runs.
+    NextTouch,
+}
+
+const MEMBIND_FLAG_PROCESS: i64 = 1 << 0;
+const MEMBIND_FLAG_THREAD: i64 = 1 << 1;
+const MEMBIND_FLAG_STRICT: i64 = 1 << 2;
+const MEMBIND_FLAG_MIGRATE: i64 = 1 << 3;
+const MEMBIND_FLAG_NOCPUBIND: i64 = 1 << 4;
+const MEMBIND_FLAG_BYNODESET: i64 = 1 << 5;
+
+/// Flags refining how a memory binding request is applied, combined by
+/// passing a slice to the `*_membind` functions.
+#[derive(Debug, PartialEq)]
+pub enum MemBindFlags {
+    Process = MEMBIND_FLAG_PROCESS as isize,
+    Thread = MEMBIND_FLAG_THREAD as isize,
+    Strict = MEMBIND_FLAG_STRICT as isize,
+    Migrate = MEMBIND_FLAG_MIGRATE as isize,
+    NoCpuBind = MEMBIND_FLAG_NOCPUBIND as isize,
+    ByNodeSet = MEMBIND_FLAG_BYNODESET as isize,
+}
+
+impl ToPrimitive for MemBindFlags {
+    fn to_i64(&self) -> Option<i64> {
+        match *self {
+            MemBindFlags::Process => Some(MEMBIND_FLAG_PROCESS),
+            MemBindFlags::Thread => Some(MEMBIND_FLAG_THREAD),
+            MemBindFlags::Strict => Some(MEMBIND_FLAG_STRICT),
+            MemBindFlags::Migrate => Some(MEMBIND_FLAG_MIGRATE),
+            MemBindFlags::NoCpuBind => Some(MEMBIND_FLAG_NOCPUBIND),
+            MemBindFlags::ByNodeSet => Some(MEMBIND_FLAG_BYNODESET),
+        }
+    }
+
+    fn to_u64(&self) -> Option<u64> {
+        self.to_i64().and_then(|x| x.to_u64())
+    }
+}
+
+impl FromPrimitive for MemBindFlags {
+    fn from_i64(n: i64) -> Option<Self> {
+        match n {
+            MEMBIND_FLAG_PROCESS => Some(MemBindFlags::Process),
+            MEMBIND_FLAG_THREAD => Some(MemBindFlags::Thread),
+            MEMBIND_FLAG_STRICT => Some(MemBindFlags::Strict),
+            MEMBIND_FLAG_MIGRATE => Some(MemBindFlags::Migrate),
+            MEMBIND_FLAG_NOCPUBIND => Some(MemBindFlags::NoCpuBind),
+            MEMBIND_FLAG_BYNODESET => Some(MemBindFlags::ByNodeSet),
+            _ => None,
+        }
+    }
+
+    fn from_u64(n: u64) -> Option<Self> {
+        FromPrimitive::from_i64(n as i64)
+    }
+}
+
+/// Errors returned by the safe memory binding wrappers.
+#[derive(Debug, PartialEq)]
+pub enum MemBindError {
+    /// The requested policy is not supported by this topology, as reported
+    /// by `hwloc_topology_get_support`.
+    UnsupportedPolicy,
+    /// The underlying hwloc call itself failed, for a reason unrelated to
+    /// policy support (e.g. an invalid nodeset, or insufficient privileges
+    /// to bind another process's memory). Carries hwloc's raw `c_int`
+    /// return code for diagnostics.
+    CallFailed(c_int),
+    /// `hwloc_alloc_membind` returned a null pointer. Unlike the other
+    /// `*_membind` calls, allocation failure carries no hwloc return code
+    /// to report.
+    AllocFailed,
+}
+
 #[cfg(target_os = "windows")]
 #[link(name = "libhwloc")]
 extern "C" {
@@ -241,16 +356,38 @@ extern "C" {
     pub fn hwloc_topology_get_flags(topology: *mut HwlocTopology) -> c_ulonglong;
     pub fn hwloc_topology_get_support(topology: *mut HwlocTopology) -> *const TopologySupport;
 
+    // === Topology Building ===
+
+    pub fn hwloc_topology_set_synthetic(
+        topology: *mut HwlocTopology,
+        description: *const c_char,
+    ) -> c_int;
+    pub fn hwloc_topology_export_synthetic(
+        topology: *mut HwlocTopology,
+        buffer: *mut c_char,
+        buflen: size_t,
+        flags: c_ulonglong,
+    ) -> c_int;
+    pub fn hwloc_alloc_setup_object(
+        topology: *mut HwlocTopology,
+        object_type: ObjectType,
+        os_index: c_uint,
+    ) -> *mut TopologyObject;
+    pub fn hwloc_insert_object_by_cpuset(
+        topology: *mut HwlocTopology,
+        object: *mut TopologyObject,
+    ) -> c_int;
+
     // === Object levels, depths and types ===
 
     pub fn hwloc_topology_get_depth(topology: *mut HwlocTopology) -> c_uint;
     pub fn hwloc_get_type_depth(topology: *mut HwlocTopology, object_type: ObjectType) -> c_int;
-    pub fn hwloc_get_depth_type(topology: *mut HwlocTopology, depth: c_uint) -> ObjectType;
-    pub fn hwloc_get_nbobjs_by_depth(topology: *mut HwlocTopology, depth: c_uint) -> c_uint;
+    pub fn hwloc_get_depth_type(topology: *mut HwlocTopology, depth: c_int) -> ObjectType;
+    pub fn hwloc_get_nbobjs_by_depth(topology: *mut HwlocTopology, depth: c_int) -> c_uint;
 
     pub fn hwloc_get_obj_by_depth(
         topology: *mut HwlocTopology,
-        depth: c_uint,
+        depth: c_int,
         idx: c_uint,
     ) -> *mut TopologyObject;
 
@@ -303,6 +440,43 @@ extern "C" {
 
     // === Memory Binding ===
 
+    pub fn hwloc_set_membind(
+        topology: *mut HwlocTopology,
+        nodeset: *const IntHwlocBitmap,
+        policy: MemBindPolicy,
+        flags: c_int,
+    ) -> c_int;
+    pub fn hwloc_get_membind(
+        topology: *mut HwlocTopology,
+        nodeset: *mut IntHwlocBitmap,
+        policy: *mut MemBindPolicy,
+        flags: c_int,
+    ) -> c_int;
+    pub fn hwloc_set_area_membind(
+        topology: *mut HwlocTopology,
+        addr: *const c_void,
+        len: size_t,
+        nodeset: *const IntHwlocBitmap,
+        policy: MemBindPolicy,
+        flags: c_int,
+    ) -> c_int;
+    pub fn hwloc_get_area_membind(
+        topology: *mut HwlocTopology,
+        addr: *const c_void,
+        len: size_t,
+        nodeset: *mut IntHwlocBitmap,
+        policy: *mut MemBindPolicy,
+        flags: c_int,
+    ) -> c_int;
+    pub fn hwloc_alloc_membind(
+        topology: *mut HwlocTopology,
+        len: size_t,
+        nodeset: *const IntHwlocBitmap,
+        policy: MemBindPolicy,
+        flags: c_int,
+    ) -> *mut c_void;
+    pub fn hwloc_free(topology: *mut HwlocTopology, addr: *mut c_void, len: size_t) -> c_int;
+
     // === Bitmap Methods ===
     pub fn hwloc_bitmap_alloc() -> *mut IntHwlocBitmap;
     pub fn hwloc_bitmap_alloc_full() -> *mut IntHwlocBitmap;
@@ -330,6 +504,36 @@ extern "C" {
         -> c_int;
     pub fn hwloc_bitmap_isfull(bitmap: *const IntHwlocBitmap) -> c_int;
     pub fn hwloc_bitmap_next(bitmap: *const IntHwlocBitmap, prev: c_int) -> c_int;
+    pub fn hwloc_bitmap_intersects(
+        left: *const IntHwlocBitmap,
+        right: *const IntHwlocBitmap,
+    ) -> c_int;
+    pub fn hwloc_bitmap_isincluded(
+        sub: *const IntHwlocBitmap,
+        super_: *const IntHwlocBitmap,
+    ) -> c_int;
+    pub fn hwloc_bitmap_and(
+        result: *mut IntHwlocBitmap,
+        left: *const IntHwlocBitmap,
+        right: *const IntHwlocBitmap,
+    );
+    pub fn hwloc_bitmap_or(
+        result: *mut IntHwlocBitmap,
+        left: *const IntHwlocBitmap,
+        right: *const IntHwlocBitmap,
+    );
+    pub fn hwloc_bitmap_xor(
+        result: *mut IntHwlocBitmap,
+        left: *const IntHwlocBitmap,
+        right: *const IntHwlocBitmap,
+    );
+    pub fn hwloc_bitmap_andnot(
+        result: *mut IntHwlocBitmap,
+        left: *const IntHwlocBitmap,
+        right: *const IntHwlocBitmap,
+    );
+    pub fn hwloc_bitmap_allbut(bitmap: *mut IntHwlocBitmap, id: c_uint);
+    pub fn hwloc_bitmap_only(bitmap: *mut IntHwlocBitmap, id: c_uint);
 
     pub fn hwloc_obj_type_snprintf(
         into: *mut c_char,
@@ -364,16 +568,38 @@ extern "C" {
     pub fn hwloc_topology_get_flags(topology: *mut HwlocTopology) -> c_ulonglong;
     pub fn hwloc_topology_get_support(topology: *mut HwlocTopology) -> *const TopologySupport;
 
+    // === Topology Building ===
+
+    pub fn hwloc_topology_set_synthetic(
+        topology: *mut HwlocTopology,
+        description: *const c_char,
+    ) -> c_int;
+    pub fn hwloc_topology_export_synthetic(
+        topology: *mut HwlocTopology,
+        buffer: *mut c_char,
+        buflen: size_t,
+        flags: c_ulonglong,
+    ) -> c_int;
+    pub fn hwloc_alloc_setup_object(
+        topology: *mut HwlocTopology,
+        object_type: ObjectType,
+        os_index: c_uint,
+    ) -> *mut TopologyObject;
+    pub fn hwloc_insert_object_by_cpuset(
+        topology: *mut HwlocTopology,
+        object: *mut TopologyObject,
+    ) -> c_int;
+
     // === Object levels, depths and types ===
 
     pub fn hwloc_topology_get_depth(topology: *mut HwlocTopology) -> c_uint;
     pub fn hwloc_get_type_depth(topology: *mut HwlocTopology, object_type: ObjectType) -> c_int;
-    pub fn hwloc_get_depth_type(topology: *mut HwlocTopology, depth: c_uint) -> ObjectType;
-    pub fn hwloc_get_nbobjs_by_depth(topology: *mut HwlocTopology, depth: c_uint) -> c_uint;
+    pub fn hwloc_get_depth_type(topology: *mut HwlocTopology, depth: c_int) -> ObjectType;
+    pub fn hwloc_get_nbobjs_by_depth(topology: *mut HwlocTopology, depth: c_int) -> c_uint;
 
     pub fn hwloc_get_obj_by_depth(
         topology: *mut HwlocTopology,
-        depth: c_uint,
+        depth: c_int,
         idx: c_uint,
     ) -> *mut TopologyObject;
 
@@ -426,6 +652,43 @@ extern "C" {
 
     // === Memory Binding ===
 
+    pub fn hwloc_set_membind(
+        topology: *mut HwlocTopology,
+        nodeset: *const IntHwlocBitmap,
+        policy: MemBindPolicy,
+        flags: c_int,
+    ) -> c_int;
+    pub fn hwloc_get_membind(
+        topology: *mut HwlocTopology,
+        nodeset: *mut IntHwlocBitmap,
+        policy: *mut MemBindPolicy,
+        flags: c_int,
+    ) -> c_int;
+    pub fn hwloc_set_area_membind(
+        topology: *mut HwlocTopology,
+        addr: *const c_void,
+        len: size_t,
+        nodeset: *const IntHwlocBitmap,
+        policy: MemBindPolicy,
+        flags: c_int,
+    ) -> c_int;
+    pub fn hwloc_get_area_membind(
+        topology: *mut HwlocTopology,
+        addr: *const c_void,
+        len: size_t,
+        nodeset: *mut IntHwlocBitmap,
+        policy: *mut MemBindPolicy,
+        flags: c_int,
+    ) -> c_int;
+    pub fn hwloc_alloc_membind(
+        topology: *mut HwlocTopology,
+        len: size_t,
+        nodeset: *const IntHwlocBitmap,
+        policy: MemBindPolicy,
+        flags: c_int,
+    ) -> *mut c_void;
+    pub fn hwloc_free(topology: *mut HwlocTopology, addr: *mut c_void, len: size_t) -> c_int;
+
     // === Bitmap Methods ===
     pub fn hwloc_bitmap_alloc() -> *mut IntHwlocBitmap;
     pub fn hwloc_bitmap_alloc_full() -> *mut IntHwlocBitmap;
@@ -453,6 +716,36 @@ extern "C" {
         -> c_int;
     pub fn hwloc_bitmap_isfull(bitmap: *const IntHwlocBitmap) -> c_int;
     pub fn hwloc_bitmap_next(bitmap: *const IntHwlocBitmap, prev: c_int) -> c_int;
+    pub fn hwloc_bitmap_intersects(
+        left: *const IntHwlocBitmap,
+        right: *const IntHwlocBitmap,
+    ) -> c_int;
+    pub fn hwloc_bitmap_isincluded(
+        sub: *const IntHwlocBitmap,
+        super_: *const IntHwlocBitmap,
+    ) -> c_int;
+    pub fn hwloc_bitmap_and(
+        result: *mut IntHwlocBitmap,
+        left: *const IntHwlocBitmap,
+        right: *const IntHwlocBitmap,
+    );
+    pub fn hwloc_bitmap_or(
+        result: *mut IntHwlocBitmap,
+        left: *const IntHwlocBitmap,
+        right: *const IntHwlocBitmap,
+    );
+    pub fn hwloc_bitmap_xor(
+        result: *mut IntHwlocBitmap,
+        left: *const IntHwlocBitmap,
+        right: *const IntHwlocBitmap,
+    );
+    pub fn hwloc_bitmap_andnot(
+        result: *mut IntHwlocBitmap,
+        left: *const IntHwlocBitmap,
+        right: *const IntHwlocBitmap,
+    );
+    pub fn hwloc_bitmap_allbut(bitmap: *mut IntHwlocBitmap, id: c_uint);
+    pub fn hwloc_bitmap_only(bitmap: *mut IntHwlocBitmap, id: c_uint);
 
     pub fn hwloc_obj_type_snprintf(
         into: *mut c_char,
@@ -471,6 +764,548 @@ extern "C" {
     pub fn hwloc_compare_types(type1: ObjectType, type2: ObjectType) -> c_int;
 }
 
+fn membind_policy_supported(topology: *mut HwlocTopology, policy: &MemBindPolicy) -> bool {
+    let support = unsafe { hwloc_topology_get_support(topology) };
+    if support.is_null() {
+        return false;
+    }
+    let support = unsafe { &*support };
+    if support.membind.is_null() {
+        return false;
+    }
+    let membind = unsafe { &*support.membind };
+    match *policy {
+        MemBindPolicy::Default => true,
+        MemBindPolicy::FirstTouch => membind.firsttouch_membind != 0,
+        MemBindPolicy::Bind => membind.bind_membind != 0,
+        MemBindPolicy::Interleave => membind.interleave_membind != 0,
+        MemBindPolicy::NextTouch => membind.nexttouch_membind != 0,
+    }
+}
+
+fn membind_flags_to_c_int(flags: &[MemBindFlags]) -> c_int {
+    flags
+        .iter()
+        .fold(0i64, |acc, flag| acc | flag.to_i64().unwrap_or(0)) as c_int
+}
+
+/// Binds the memory of the calling process (or thread, with `MemBindFlags::Thread`)
+/// to `nodeset`, following `policy`.
+///
+/// Returns `Err(MemBindError::UnsupportedPolicy)` without calling into hwloc if
+/// `hwloc_topology_get_support` reports that `policy` is not supported, mirroring
+/// how callers are expected to probe `TopologySupport` before binding.
+pub fn set_membind(
+    topology: *mut HwlocTopology,
+    nodeset: &IntHwlocBitmap,
+    policy: MemBindPolicy,
+    flags: &[MemBindFlags],
+) -> Result<(), MemBindError> {
+    if !membind_policy_supported(topology, &policy) {
+        return Err(MemBindError::UnsupportedPolicy);
+    }
+    let raw_flags = membind_flags_to_c_int(flags);
+    let result = unsafe { hwloc_set_membind(topology, nodeset, policy, raw_flags) };
+    if result < 0 {
+        Err(MemBindError::CallFailed(result))
+    } else {
+        Ok(())
+    }
+}
+
+/// Retrieves the current memory binding policy and nodeset into `nodeset`.
+pub fn get_membind(
+    topology: *mut HwlocTopology,
+    nodeset: &mut IntHwlocBitmap,
+    flags: &[MemBindFlags],
+) -> Result<MemBindPolicy, MemBindError> {
+    let raw_flags = membind_flags_to_c_int(flags);
+    let mut policy = MemBindPolicy::Default;
+    let result = unsafe { hwloc_get_membind(topology, nodeset, &mut policy, raw_flags) };
+    if result < 0 {
+        Err(MemBindError::CallFailed(result))
+    } else {
+        Ok(policy)
+    }
+}
+
+/// Binds the memory backing `addr..addr+len` to `nodeset`, following `policy`.
+pub fn set_area_membind(
+    topology: *mut HwlocTopology,
+    addr: *const c_void,
+    len: size_t,
+    nodeset: &IntHwlocBitmap,
+    policy: MemBindPolicy,
+    flags: &[MemBindFlags],
+) -> Result<(), MemBindError> {
+    if !membind_policy_supported(topology, &policy) {
+        return Err(MemBindError::UnsupportedPolicy);
+    }
+    let raw_flags = membind_flags_to_c_int(flags);
+    let result = unsafe { hwloc_set_area_membind(topology, addr, len, nodeset, policy, raw_flags) };
+    if result < 0 {
+        Err(MemBindError::CallFailed(result))
+    } else {
+        Ok(())
+    }
+}
+
+/// Retrieves the memory binding policy and nodeset backing `addr..addr+len`.
+pub fn get_area_membind(
+    topology: *mut HwlocTopology,
+    addr: *const c_void,
+    len: size_t,
+    nodeset: &mut IntHwlocBitmap,
+    flags: &[MemBindFlags],
+) -> Result<MemBindPolicy, MemBindError> {
+    let raw_flags = membind_flags_to_c_int(flags);
+    let mut policy = MemBindPolicy::Default;
+    let result =
+        unsafe { hwloc_get_area_membind(topology, addr, len, nodeset, &mut policy, raw_flags) };
+    if result < 0 {
+        Err(MemBindError::CallFailed(result))
+    } else {
+        Ok(policy)
+    }
+}
+
+/// Allocates `len` bytes of memory bound to `nodeset` following `policy`.
+pub fn alloc_membind(
+    topology: *mut HwlocTopology,
+    len: size_t,
+    nodeset: &IntHwlocBitmap,
+    policy: MemBindPolicy,
+    flags: &[MemBindFlags],
+) -> Result<*mut c_void, MemBindError> {
+    if !membind_policy_supported(topology, &policy) {
+        return Err(MemBindError::UnsupportedPolicy);
+    }
+    let raw_flags = membind_flags_to_c_int(flags);
+    let ptr = unsafe { hwloc_alloc_membind(topology, len, nodeset, policy, raw_flags) };
+    if ptr.is_null() {
+        Err(MemBindError::AllocFailed)
+    } else {
+        Ok(ptr)
+    }
+}
+
+/// Frees memory previously returned by `alloc_membind`.
+pub fn free_membind(
+    topology: *mut HwlocTopology,
+    addr: *mut c_void,
+    len: size_t,
+) -> Result<(), MemBindError> {
+    let result = unsafe { hwloc_free(topology, addr, len) };
+    if result < 0 {
+        Err(MemBindError::CallFailed(result))
+    } else {
+        Ok(())
+    }
+}
+
+/// Walks down from the root of `topology` towards `set`, returning the
+/// largest single object whose cpuset is fully included in `set`.
+///
+/// Returns `None` if `set` does not intersect the root object's cpuset at
+/// all, i.e. there is nothing inside `set` to return.
+///
+/// Borrows `topology` for `'a` so the returned reference cannot outlive the
+/// topology it points into; `*mut HwlocTopology` alone carries no lifetime
+/// the compiler could use to enforce that.
+pub fn get_first_largest_obj_inside_cpuset<'a>(
+    topology: &'a HwlocTopology,
+    set: &IntHwlocBitmap,
+) -> Option<&'a TopologyObject> {
+    let topology = topology as *const HwlocTopology as *mut HwlocTopology;
+    let root = unsafe { hwloc_get_obj_by_depth(topology, 0, 0) };
+    if root.is_null() {
+        return None;
+    }
+    if unsafe { hwloc_bitmap_intersects((*root).cpuset, set) } == 0 {
+        return None;
+    }
+
+    let mut current = root;
+    loop {
+        if unsafe { hwloc_bitmap_isincluded((*current).cpuset, set) } != 0 {
+            return Some(unsafe { &*current });
+        }
+
+        let arity = unsafe { (*current).arity } as isize;
+        let children = unsafe { (*current).children };
+        let first_intersecting_child =
+            (0..arity)
+                .map(|i| unsafe { *children.offset(i) })
+                .find(|&child| {
+                    !child.is_null()
+                        && unsafe { hwloc_bitmap_intersects((*child).cpuset, set) } != 0
+                });
+
+        match first_intersecting_child {
+            Some(child) => current = child,
+            None => return Some(unsafe { &*current }),
+        }
+    }
+}
+
+/// Decomposes `set` into the whole topology objects it is made of, largest
+/// first, by repeatedly taking `get_first_largest_obj_inside_cpuset` and
+/// clearing the returned object's cpuset from a working copy of `set`.
+pub fn largest_objs_inside_cpuset<'a>(
+    topology: &'a HwlocTopology,
+    set: &IntHwlocBitmap,
+) -> Vec<&'a TopologyObject> {
+    let mut remaining = Bitmap::dup_raw(set);
+    let mut result = Vec::new();
+
+    while unsafe { hwloc_bitmap_iszero(remaining.as_raw()) } == 0 {
+        match get_first_largest_obj_inside_cpuset(topology, unsafe { &*remaining.as_raw() }) {
+            Some(obj) => {
+                remaining = remaining - Bitmap::dup_raw(obj.cpuset);
+                result.push(obj);
+            }
+            None => break,
+        }
+    }
+
+    result
+}
+
+/// An owned hwloc bitmap, used to represent cpusets and nodesets.
+///
+/// `Bitmap` allocates its backing `IntHwlocBitmap` on construction and frees
+/// it on drop, so masks can be combined with ordinary operator syntax
+/// (`a & b`, `a | b`, `!a`, `a - b`) instead of juggling raw pointers and
+/// manually matching up allocations and frees. Cpusets and nodesets
+/// obtained elsewhere as raw `IntHwlocBitmap` pointers can be brought in
+/// via `from_raw` (taking ownership) or `dup_raw` (copying; use this for a
+/// `TopologyObject`'s `cpuset`, which the topology owns) to use the same
+/// operators.
+pub struct Bitmap {
+    raw: *mut IntHwlocBitmap,
+}
+
+impl Bitmap {
+    /// Creates an empty bitmap.
+    pub fn new() -> Bitmap {
+        Bitmap {
+            raw: unsafe { hwloc_bitmap_alloc() },
+        }
+    }
+
+    /// Creates a bitmap with every index set except `id`.
+    pub fn allbut(id: c_uint) -> Bitmap {
+        let bitmap = Bitmap::new();
+        unsafe { hwloc_bitmap_allbut(bitmap.raw, id) };
+        bitmap
+    }
+
+    /// Creates a bitmap with only `id` set.
+    pub fn only(id: c_uint) -> Bitmap {
+        let bitmap = Bitmap::new();
+        unsafe { hwloc_bitmap_only(bitmap.raw, id) };
+        bitmap
+    }
+
+    /// Takes ownership of an existing raw `IntHwlocBitmap` allocation, e.g.
+    /// one returned by `hwloc_bitmap_alloc` or `hwloc_bitmap_dup`. The
+    /// allocation is freed when the returned `Bitmap` is dropped, so callers
+    /// must not free `raw` themselves afterwards. A `TopologyObject`'s
+    /// `cpuset`/`nodeset` is owned by its topology; copy it with `dup_raw`.
+    pub unsafe fn from_raw(raw: *mut IntHwlocBitmap) -> Bitmap {
+        Bitmap { raw }
+    }
+
+    /// Copies an existing raw `IntHwlocBitmap`, e.g. a `TopologyObject`'s
+    /// `cpuset`/`nodeset`, into a new owned `Bitmap`, leaving `raw` itself
+    /// untouched.
+    pub fn dup_raw(raw: *const IntHwlocBitmap) -> Bitmap {
+        Bitmap {
+            raw: unsafe { hwloc_bitmap_dup(raw) },
+        }
+    }
+
+    /// Returns the underlying pointer for passing to FFI calls that only
+    /// read the bitmap.
+    pub fn as_raw(&self) -> *const IntHwlocBitmap {
+        self.raw
+    }
+
+    /// Returns the underlying pointer for passing to FFI calls that mutate
+    /// the bitmap in place.
+    pub fn as_raw_mut(&mut self) -> *mut IntHwlocBitmap {
+        self.raw
+    }
+}
+
+impl Clone for Bitmap {
+    fn clone(&self) -> Bitmap {
+        Bitmap {
+            raw: unsafe { hwloc_bitmap_dup(self.raw) },
+        }
+    }
+}
+
+impl Drop for Bitmap {
+    fn drop(&mut self) {
+        unsafe { hwloc_bitmap_free(self.raw) };
+    }
+}
+
+impl Default for Bitmap {
+    fn default() -> Bitmap {
+        Bitmap::new()
+    }
+}
+
+impl BitAnd for Bitmap {
+    type Output = Bitmap;
+
+    fn bitand(self, rhs: Bitmap) -> Bitmap {
+        let result = Bitmap::new();
+        unsafe { hwloc_bitmap_and(result.raw, self.raw, rhs.raw) };
+        result
+    }
+}
+
+impl BitOr for Bitmap {
+    type Output = Bitmap;
+
+    fn bitor(self, rhs: Bitmap) -> Bitmap {
+        let result = Bitmap::new();
+        unsafe { hwloc_bitmap_or(result.raw, self.raw, rhs.raw) };
+        result
+    }
+}
+
+impl BitXor for Bitmap {
+    type Output = Bitmap;
+
+    fn bitxor(self, rhs: Bitmap) -> Bitmap {
+        let result = Bitmap::new();
+        unsafe { hwloc_bitmap_xor(result.raw, self.raw, rhs.raw) };
+        result
+    }
+}
+
+impl Not for Bitmap {
+    type Output = Bitmap;
+
+    fn not(self) -> Bitmap {
+        let result = Bitmap::new();
+        unsafe { hwloc_bitmap_not(result.raw, self.raw) };
+        result
+    }
+}
+
+impl Sub for Bitmap {
+    type Output = Bitmap;
+
+    fn sub(self, rhs: Bitmap) -> Bitmap {
+        let result = Bitmap::new();
+        unsafe { hwloc_bitmap_andnot(result.raw, self.raw, rhs.raw) };
+        result
+    }
+}
+
+/// Errors that can occur while building a topology programmatically, either
+/// from a synthetic description or by inserting objects one at a time.
+#[derive(Debug, PartialEq)]
+pub enum SyntheticTopologyError {
+    /// The description string could not be parsed by hwloc, or contained an
+    /// interior NUL byte.
+    InvalidDescription,
+    /// The object could not be allocated or inserted into the topology.
+    InsertFailed,
+}
+
+/// Cache attributes to apply to an object inserted via `insert_cache_object`.
+#[derive(Debug, Clone, Copy)]
+pub struct CacheAttributes {
+    pub cache_type: CacheType,
+    pub depth: c_uint,
+    pub size: c_ulonglong,
+    pub linesize: c_uint,
+    pub associativity: c_int,
+}
+
+/// Replaces `topology`'s discovery with a synthetic description such as
+/// `"Package:2 Core:4 PU:2"`. Must be called after `hwloc_topology_init` and
+/// before `hwloc_topology_load`, letting tests and simulations run against a
+/// fixed layout instead of the real machine.
+pub fn set_synthetic(
+    topology: *mut HwlocTopology,
+    description: &str,
+) -> Result<(), SyntheticTopologyError> {
+    let description =
+        CString::new(description).map_err(|_| SyntheticTopologyError::InvalidDescription)?;
+    let result = unsafe { hwloc_topology_set_synthetic(topology, description.as_ptr()) };
+    if result < 0 {
+        Err(SyntheticTopologyError::InvalidDescription)
+    } else {
+        Ok(())
+    }
+}
+
+/// Largest buffer `export_synthetic` will grow to before giving up.
+const EXPORT_SYNTHETIC_MAX_BUFFER_LEN: usize = 1024 * 1024;
+
+/// Serializes a loaded topology back into a synthetic description string,
+/// the inverse of `set_synthetic`.
+///
+/// `hwloc_topology_export_synthetic` follows the `snprintf` convention: a
+/// return value at or beyond the buffer length means the description was
+/// truncated, not that the call failed. When that happens, the buffer is
+/// doubled and the export retried, up to `EXPORT_SYNTHETIC_MAX_BUFFER_LEN`.
+pub fn export_synthetic(topology: *mut HwlocTopology) -> Option<String> {
+    let mut buffer_len = 4096;
+    loop {
+        let mut buffer = vec![0 as c_char; buffer_len];
+        let result = unsafe {
+            hwloc_topology_export_synthetic(
+                topology,
+                buffer.as_mut_ptr(),
+                buffer.len() as size_t,
+                0,
+            )
+        };
+        if result < 0 {
+            return None;
+        }
+        if result as usize >= buffer.len() {
+            if buffer_len >= EXPORT_SYNTHETIC_MAX_BUFFER_LEN {
+                return None;
+            }
+            buffer_len *= 2;
+            continue;
+        }
+        let exported = unsafe { CStr::from_ptr(buffer.as_ptr()) };
+        return Some(exported.to_string_lossy().into_owned());
+    }
+}
+
+/// Allocates an object of `object_type`/`os_index`, attaches `cpuset` to it,
+/// and inserts it into `topology`.
+///
+/// This is the lower-level counterpart to `set_synthetic`: it lets callers
+/// hardwire a known machine one object at a time instead of describing it
+/// as a single synthetic string.
+pub fn insert_object_by_cpuset(
+    topology: *mut HwlocTopology,
+    object_type: ObjectType,
+    os_index: c_uint,
+    cpuset: &IntHwlocBitmap,
+) -> Result<(), SyntheticTopologyError> {
+    let obj = unsafe { hwloc_alloc_setup_object(topology, object_type, os_index) };
+    if obj.is_null() {
+        return Err(SyntheticTopologyError::InsertFailed);
+    }
+    unsafe {
+        hwloc_bitmap_free((*obj).cpuset);
+        (*obj).cpuset = hwloc_bitmap_dup(cpuset);
+    }
+    let result = unsafe { hwloc_insert_object_by_cpuset(topology, obj) };
+    if result < 0 {
+        Err(SyntheticTopologyError::InsertFailed)
+    } else {
+        Ok(())
+    }
+}
+
+/// Allocates a cache object (e.g. `ObjectType::L1Cache`) with `cpuset` and
+/// `attrs`, and inserts it into `topology`.
+pub fn insert_cache_object(
+    topology: *mut HwlocTopology,
+    cache_type: ObjectType,
+    os_index: c_uint,
+    cpuset: &IntHwlocBitmap,
+    attrs: CacheAttributes,
+) -> Result<(), SyntheticTopologyError> {
+    let obj = unsafe { hwloc_alloc_setup_object(topology, cache_type, os_index) };
+    if obj.is_null() {
+        return Err(SyntheticTopologyError::InsertFailed);
+    }
+    unsafe {
+        hwloc_bitmap_free((*obj).cpuset);
+        (*obj).cpuset = hwloc_bitmap_dup(cpuset);
+        (*obj).set_cache_attributes(
+            attrs.cache_type,
+            attrs.depth,
+            attrs.size,
+            attrs.linesize,
+            attrs.associativity,
+        );
+    }
+    let result = unsafe { hwloc_insert_object_by_cpuset(topology, obj) };
+    if result < 0 {
+        Err(SyntheticTopologyError::InsertFailed)
+    } else {
+        Ok(())
+    }
+}
+
+/// Returns the depth at which `object_type` lives in `topology`.
+///
+/// NUMA nodes, bridges, PCI and OS devices, Misc objects and memory caches
+/// live at virtual depths rather than in the normal tree, so those come
+/// back as the matching `TypeDepthError` variant instead of a `u32`, along
+/// with the `TypeDepthUnknown`/`TypeDepthMultiple` cases already returned
+/// by `hwloc_get_type_depth`.
+pub fn depth_for_type(
+    topology: *mut HwlocTopology,
+    object_type: ObjectType,
+) -> Result<u32, TypeDepthError> {
+    match unsafe { hwloc_get_type_depth(topology, object_type) } {
+        depth if depth >= 0 => Ok(depth as u32),
+        -1 => Err(TypeDepthError::TypeDepthUnknown),
+        -2 => Err(TypeDepthError::TypeDepthMultiple),
+        -3 => Err(TypeDepthError::TypeDepthNumaNode),
+        -4 => Err(TypeDepthError::TypeDepthBridge),
+        -5 => Err(TypeDepthError::TypeDepthPCIDevice),
+        -6 => Err(TypeDepthError::TypeDepthOSDevice),
+        -7 => Err(TypeDepthError::TypeDepthMisc),
+        -8 => Err(TypeDepthError::TypeDepthMemcache),
+        _ => Err(TypeDepthError::Unkown),
+    }
+}
+
+/// Returns the object type found at `depth` in `topology`. `depth` may be
+/// one of hwloc's negative virtual depths, e.g. `TypeDepthError::as_raw_depth`
+/// for a NUMA node or PCI device.
+pub fn type_at_depth(topology: *mut HwlocTopology, depth: i32) -> ObjectType {
+    unsafe { hwloc_get_depth_type(topology, depth) }
+}
+
+/// Returns the number of objects at `depth` in `topology`. `depth` may be
+/// one of hwloc's negative virtual depths, e.g. `TypeDepthError::as_raw_depth`
+/// for a NUMA node or PCI device.
+pub fn num_objects_at_depth(topology: *mut HwlocTopology, depth: i32) -> u32 {
+    unsafe { hwloc_get_nbobjs_by_depth(topology, depth) as u32 }
+}
+
+/// Iterates over every object at `depth` in `topology`, including the
+/// virtual depths reachable through `TypeDepthError::as_raw_depth`, so NUMA
+/// nodes, bridges, PCI/OS devices and Misc objects become reachable through
+/// a typed iterator instead of magic integers.
+///
+/// Borrows `topology` for `'a` so the returned references cannot outlive
+/// the topology they point into.
+pub fn objects_at_depth<'a>(
+    topology: &'a HwlocTopology,
+    depth: i32,
+) -> impl Iterator<Item = &'a TopologyObject> {
+    let raw_topology = topology as *const HwlocTopology as *mut HwlocTopology;
+    let count = num_objects_at_depth(raw_topology, depth);
+    (0..count).filter_map(move |idx| {
+        let obj = unsafe { hwloc_get_obj_by_depth(raw_topology, depth, idx as c_uint) };
+        if obj.is_null() {
+            None
+        } else {
+            Some(unsafe { &*obj })
+        }
+    })
+}
+
 #[cfg(test)]
 mod tests {
 
@@ -490,4 +1325,160 @@ mod tests {
         assert!(ObjectType::Machine < ObjectType::PU);
         assert!(ObjectType::PU > ObjectType::L1Cache);
     }
+
+    #[test]
+    fn should_treat_unrelated_types_as_unordered() {
+        assert_eq!(
+            None,
+            ObjectType::NUMANode.partial_cmp(&ObjectType::PCIDevice)
+        );
+        assert!(ObjectType::NUMANode != ObjectType::PCIDevice);
+    }
+
+    #[test]
+    fn should_combine_bitmaps_with_operators() {
+        let a = Bitmap::only(1);
+        let b = Bitmap::only(2);
+
+        let unioned = a.clone() | b.clone();
+        assert_eq!(1, unsafe { hwloc_bitmap_isset(unioned.as_raw(), 1) });
+        assert_eq!(1, unsafe { hwloc_bitmap_isset(unioned.as_raw(), 2) });
+
+        let intersected = a.clone() & b.clone();
+        assert_eq!(1, unsafe { hwloc_bitmap_iszero(intersected.as_raw()) });
+
+        let subtracted = unioned - a;
+        assert_eq!(0, unsafe { hwloc_bitmap_isset(subtracted.as_raw(), 1) });
+        assert_eq!(1, unsafe { hwloc_bitmap_isset(subtracted.as_raw(), 2) });
+    }
+
+    #[test]
+    fn should_default_to_an_empty_bitmap() {
+        let bitmap = Bitmap::default();
+        assert_eq!(1, unsafe { hwloc_bitmap_iszero(bitmap.as_raw()) });
+    }
+
+    #[test]
+    fn should_round_trip_a_synthetic_topology() {
+        let mut topology: *mut HwlocTopology = std::ptr::null_mut();
+        unsafe {
+            assert_eq!(0, hwloc_topology_init(&mut topology));
+        }
+        set_synthetic(topology, "Package:2 Core:2 PU:2").unwrap();
+        unsafe {
+            assert_eq!(0, hwloc_topology_load(topology));
+        }
+
+        let exported = export_synthetic(topology).expect("export should succeed");
+        assert!(exported.contains("Package"));
+
+        unsafe {
+            hwloc_topology_destroy(topology);
+        }
+    }
+
+    #[test]
+    fn should_round_trip_membind_policy_or_report_unsupported() {
+        let mut topology: *mut HwlocTopology = std::ptr::null_mut();
+        unsafe {
+            assert_eq!(0, hwloc_topology_init(&mut topology));
+        }
+        set_synthetic(topology, "Package:2 Core:2 PU:2").unwrap();
+        unsafe {
+            assert_eq!(0, hwloc_topology_load(topology));
+        }
+
+        let nodeset = Bitmap::only(0);
+        let mut readback = Bitmap::new();
+        match set_membind(
+            topology,
+            unsafe { &*nodeset.as_raw() },
+            MemBindPolicy::Bind,
+            &[],
+        ) {
+            Ok(()) => {
+                let policy = get_membind(topology, unsafe { &mut *readback.as_raw_mut() }, &[])
+                    .expect("get_membind should succeed after a successful set_membind");
+                assert_eq!(MemBindPolicy::Bind, policy);
+            }
+            Err(MemBindError::UnsupportedPolicy) => {
+                // CI sandboxes commonly lack real NUMA/membind support; that's
+                // still a well-formed, documented outcome.
+            }
+            Err(other) => panic!("unexpected membind error: {:?}", other),
+        }
+
+        unsafe {
+            hwloc_topology_destroy(topology);
+        }
+    }
+
+    #[test]
+    fn should_alloc_and_free_membind_or_report_unsupported() {
+        let mut topology: *mut HwlocTopology = std::ptr::null_mut();
+        unsafe {
+            assert_eq!(0, hwloc_topology_init(&mut topology));
+        }
+        set_synthetic(topology, "Package:2 Core:2 PU:2").unwrap();
+        unsafe {
+            assert_eq!(0, hwloc_topology_load(topology));
+        }
+
+        let nodeset = Bitmap::only(0);
+        match alloc_membind(
+            topology,
+            4096,
+            unsafe { &*nodeset.as_raw() },
+            MemBindPolicy::Bind,
+            &[],
+        ) {
+            Ok(ptr) => {
+                free_membind(topology, ptr, 4096)
+                    .expect("freeing a successful allocation should succeed");
+            }
+            Err(MemBindError::UnsupportedPolicy) => {
+                // CI sandboxes commonly lack real NUMA/membind support; that's
+                // still a well-formed, documented outcome.
+            }
+            Err(other) => panic!("unexpected membind error: {:?}", other),
+        }
+
+        unsafe {
+            hwloc_topology_destroy(topology);
+        }
+    }
+
+    #[test]
+    fn should_decompose_cpuset_into_largest_enclosed_objects() {
+        let mut topology: *mut HwlocTopology = std::ptr::null_mut();
+        unsafe {
+            assert_eq!(0, hwloc_topology_init(&mut topology));
+        }
+        set_synthetic(topology, "Package:2 Core:2 PU:2").unwrap();
+        unsafe {
+            assert_eq!(0, hwloc_topology_load(topology));
+        }
+
+        let topology_ref = unsafe { &*topology };
+        let root = unsafe { &*hwloc_get_obj_by_depth(topology, 0, 0) };
+        let root_cpuset = unsafe { &*root.cpuset };
+
+        let whole_root = largest_objs_inside_cpuset(topology_ref, root_cpuset);
+        assert_eq!(1, whole_root.len());
+        assert_eq!(root as *const _, whole_root[0] as *const _);
+
+        let package_depth = depth_for_type(topology, ObjectType::Package).expect("Package depth");
+        let package = objects_at_depth(topology_ref, package_depth as i32)
+            .next()
+            .expect("at least one Package object");
+        let package_cpuset = unsafe { &*package.cpuset };
+
+        let just_package = largest_objs_inside_cpuset(topology_ref, package_cpuset);
+        assert_eq!(1, just_package.len());
+        assert_eq!(package as *const _, just_package[0] as *const _);
+
+        unsafe {
+            hwloc_topology_destroy(topology);
+        }
+    }
 }